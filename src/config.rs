@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use crossterm::event::KeyCode;
+use serde::Deserialize;
+
+/// Keyboard shortcuts for the app's controls, loaded from the user's config
+/// file and falling back to these defaults for anything unset.
+#[derive(Clone, Copy)]
+pub struct KeyBindings {
+    pub switch: KeyCode,
+    pub fetch: KeyCode,
+    pub refresh: KeyCode,
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub quit: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            switch: KeyCode::Char('s'),
+            fetch: KeyCode::Char('f'),
+            refresh: KeyCode::Char('r'),
+            up: KeyCode::Up,
+            down: KeyCode::Down,
+            quit: KeyCode::Char('q'),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct ConfigFile {
+    keys: Option<KeyTable>,
+}
+
+#[derive(Deserialize, Default)]
+struct KeyTable {
+    switch: Option<String>,
+    fetch: Option<String>,
+    refresh: Option<String>,
+    up: Option<String>,
+    down: Option<String>,
+    quit: Option<String>,
+}
+
+/// Loads keybindings from `~/.config/nuno/config.toml` (or
+/// `$XDG_CONFIG_HOME/nuno/config.toml`), falling back to the defaults when
+/// the file is absent, unreadable, or a key is unspecified.
+pub fn load_key_bindings() -> KeyBindings {
+    let defaults = KeyBindings::default();
+
+    let Some(path) = config_path() else {
+        return defaults;
+    };
+
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return defaults;
+    };
+
+    let Ok(config) = toml::from_str::<ConfigFile>(&contents) else {
+        return defaults;
+    };
+
+    let Some(keys) = config.keys else {
+        return defaults;
+    };
+
+    KeyBindings {
+        switch: keys
+            .switch
+            .and_then(|key| parse_key(&key))
+            .unwrap_or(defaults.switch),
+        fetch: keys
+            .fetch
+            .and_then(|key| parse_key(&key))
+            .unwrap_or(defaults.fetch),
+        refresh: keys
+            .refresh
+            .and_then(|key| parse_key(&key))
+            .unwrap_or(defaults.refresh),
+        up: keys
+            .up
+            .and_then(|key| parse_key(&key))
+            .unwrap_or(defaults.up),
+        down: keys
+            .down
+            .and_then(|key| parse_key(&key))
+            .unwrap_or(defaults.down),
+        quit: keys
+            .quit
+            .and_then(|key| parse_key(&key))
+            .unwrap_or(defaults.quit),
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("nuno/config.toml"))
+}
+
+/// Parses a config key string ("s", "Up", "Enter") into a `KeyCode`.
+fn parse_key(key: &str) -> Option<KeyCode> {
+    match key.to_ascii_lowercase().as_str() {
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        "enter" => Some(KeyCode::Enter),
+        "esc" | "escape" => Some(KeyCode::Esc),
+        "tab" => Some(KeyCode::Tab),
+        "backspace" => Some(KeyCode::Backspace),
+        // 'c' is reserved for the non-remappable Create control.
+        "c" => None,
+        _ if key.chars().count() == 1 => key.chars().next().map(KeyCode::Char),
+        _ => None,
+    }
+}
@@ -1,4 +1,42 @@
-use git2::{Branch, Repository};
+use std::sync::mpsc;
+use std::thread;
+
+use git2::{Branch, Cred, CredentialType, Repository};
+
+/// Incremental updates emitted while a background fetch is in flight,
+/// streamed back to the UI thread over an `mpsc` channel.
+pub enum FetchProgress {
+    Update {
+        received_objects: usize,
+        total_objects: usize,
+        received_bytes: usize,
+    },
+    Done(Result<(), String>),
+}
+
+/// A local branch paired with the Unix epoch timestamp of its tip commit.
+///
+/// `last_commit_time` is `None` when the tip commit can't be resolved (e.g. an
+/// unborn branch), in which case the branch should sort to the bottom of any
+/// recency-ordered list.
+///
+/// `ahead_behind` is computed each time the list is built (two commit peels
+/// plus a `graph_ahead_behind` walk per branch); it's `None` when the branch
+/// has no upstream configured or the counts can't be computed.
+pub struct BranchInfo {
+    pub name: String,
+    pub last_commit_time: Option<i64>,
+    pub ahead_behind: Option<(usize, usize)>,
+}
+
+/// A compact summary of `git status` for the working tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkingTreeState {
+    Clean,
+    IndexModified,
+    WorktreeModified,
+    Conflicted,
+}
 
 pub struct BranchManager<'repo> {
     repo: &'repo Repository,
@@ -29,12 +67,68 @@ impl<'repo> BranchManager<'repo> {
         Ok(())
     }
 
-    pub fn get_all_local_branch_names(&self) -> Result<Vec<String>, git2::Error> {
-        Ok(self
+    /// Returns every local branch with its tip commit's timestamp and
+    /// ahead/behind counts, ordered newest first. Branches whose tip commit
+    /// can't be resolved sink to the bottom rather than erroring out the
+    /// whole list.
+    pub fn get_branches_sorted(&self) -> Result<Vec<BranchInfo>, git2::Error> {
+        let mut branches: Vec<BranchInfo> = self
             .local_branches
             .iter()
-            .filter_map(|branch| branch.name().ok()?.map(String::from))
-            .collect())
+            .filter_map(|branch| {
+                let name = branch.name().ok()??.to_string();
+                let last_commit_time = branch
+                    .get()
+                    .peel_to_commit()
+                    .ok()
+                    .map(|commit| commit.time().seconds());
+                let ahead_behind = self.ahead_behind(branch).ok().flatten();
+                Some(BranchInfo {
+                    name,
+                    last_commit_time,
+                    ahead_behind,
+                })
+            })
+            .collect();
+
+        branches.sort_by(|a, b| match (a.last_commit_time, b.last_commit_time) {
+            (Some(a_time), Some(b_time)) => b_time.cmp(&a_time),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+
+        Ok(branches)
+    }
+
+    /// Returns how many commits `branch` is ahead/behind its upstream, or
+    /// `None` when the branch has no upstream configured.
+    pub fn ahead_behind(&self, branch: &Branch) -> Result<Option<(usize, usize)>, git2::Error> {
+        let upstream = match branch.upstream() {
+            Ok(upstream) => upstream,
+            Err(_) => return Ok(None),
+        };
+
+        let local_oid = branch.get().peel_to_commit()?.id();
+        let upstream_oid = upstream.get().peel_to_commit()?.id();
+
+        self.repo
+            .graph_ahead_behind(local_oid, upstream_oid)
+            .map(Some)
+    }
+
+    /// Creates a new local branch named `name` off the current HEAD commit
+    /// and refreshes `local_branches` so it's immediately visible.
+    pub fn create_branch(&mut self, name: &str) -> Result<(), git2::Error> {
+        let head_commit = self.repo.head()?.peel_to_commit()?;
+        self.repo.branch(name, &head_commit, false)?;
+        self.refresh_branches()
+    }
+
+    pub fn find_branch(&self, name: &str) -> Option<&Branch<'repo>> {
+        self.local_branches
+            .iter()
+            .find(|branch| branch.name().ok().flatten() == Some(name))
     }
 
     pub fn get_current_branch(&self) -> Result<String, git2::Error> {
@@ -49,15 +143,57 @@ impl<'repo> BranchManager<'repo> {
         }
     }
 
-    pub fn switch_to_branch(&self, branch: &Branch) -> Result<(), git2::Error> {
+    /// Summarizes the working tree into a single state by folding over
+    /// `repo.statuses(None)`. Conflicts take priority over worktree edits,
+    /// which take priority over index-only edits.
+    pub fn working_tree_state(&self) -> Result<WorkingTreeState, git2::Error> {
+        let statuses = self.repo.statuses(None)?;
+        let mut state = WorkingTreeState::Clean;
+
+        for entry in statuses.iter() {
+            let flags = entry.status();
+
+            if flags.contains(git2::Status::CONFLICTED) {
+                return Ok(WorkingTreeState::Conflicted);
+            }
+
+            if flags.intersects(
+                git2::Status::WT_MODIFIED | git2::Status::WT_NEW | git2::Status::WT_DELETED,
+            ) {
+                state = WorkingTreeState::WorktreeModified;
+            } else if state == WorkingTreeState::Clean
+                && flags.intersects(
+                    git2::Status::INDEX_MODIFIED
+                        | git2::Status::INDEX_NEW
+                        | git2::Status::INDEX_DELETED,
+                )
+            {
+                state = WorkingTreeState::IndexModified;
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Switches HEAD to `branch`. Refuses when the working tree is dirty
+    /// unless `force` is set, and always refuses when it's conflicted, since
+    /// force-checking out over an unresolved merge would discard it.
+    pub fn switch_to_branch(&self, branch: &Branch, force: bool) -> Result<(), git2::Error> {
         let branch_name = branch
             .name()?
             .ok_or_else(|| git2::Error::from_str("Invalid UTF-8 in branch name."))?;
 
         let current_head_name = self.get_current_branch()?;
+        let state = self.working_tree_state()?;
 
-        let statuses = self.repo.statuses(None)?;
-        if !statuses.is_empty() {
+        if state == WorkingTreeState::Conflicted {
+            return Err(git2::Error::from_str(&format!(
+                "Branch {} has unresolved merge conflicts",
+                current_head_name,
+            )));
+        }
+
+        if !force && state != WorkingTreeState::Clean {
             return Err(git2::Error::from_str(&format!(
                 "Uncommitted local changes on branch {}",
                 current_head_name,
@@ -65,27 +201,109 @@ impl<'repo> BranchManager<'repo> {
         }
 
         let mut opts = git2::build::CheckoutBuilder::new();
+        if force {
+            opts.force();
+        }
 
         self.repo.set_head(&format!("refs/heads/{}", branch_name))?;
         self.repo.checkout_head(Some(&mut opts))
     }
 
-    pub fn fetch_on_branch(&self, branch: &Branch) -> Result<(), git2::Error> {
-        let branch_name = branch
-            .name()?
-            .ok_or_else(|| git2::Error::from_str("Invalid UTF-8 in branch name"))?;
+    /// Fetches `branch_name` from `origin` on a background thread, streaming
+    /// `FetchProgress` updates back over the returned channel as objects
+    /// arrive so the UI thread can keep drawing while the transfer runs.
+    pub fn spawn_fetch_with_progress(&self, branch_name: String) -> mpsc::Receiver<FetchProgress> {
+        let (tx, rx) = mpsc::channel();
+        let repo_path = self.repo.path().to_path_buf();
 
-        let mut remote = self.repo.find_remote("origin")?;
-        let refspec = format!(
-            "+refs/heads/{}:refs/remotes/origin/{}",
-            branch_name, branch_name
-        );
+        thread::spawn(move || {
+            let result = (|| -> Result<(), git2::Error> {
+                let repo = Repository::open(&repo_path)?;
+                let mut remote = repo.find_remote("origin")?;
+                let refspec = format!("+refs/heads/{0}:refs/remotes/origin/{0}", branch_name);
 
-        let mut fetch_options = git2::FetchOptions::new();
-        fetch_options.download_tags(git2::AutotagOption::None);
+                let config = repo.config()?;
+                let progress_tx = tx.clone();
 
-        remote.fetch(&[&refspec], Some(&mut fetch_options), None)?;
+                let mut callbacks = git2::RemoteCallbacks::new();
+                callbacks.credentials(build_credentials_callback(config));
+                callbacks.transfer_progress(move |progress| {
+                    let _ = progress_tx.send(FetchProgress::Update {
+                        received_objects: progress.received_objects(),
+                        total_objects: progress.total_objects(),
+                        received_bytes: progress.received_bytes(),
+                    });
+                    true
+                });
 
-        Ok(())
+                let mut fetch_options = git2::FetchOptions::new();
+                fetch_options.download_tags(git2::AutotagOption::None);
+                fetch_options.remote_callbacks(callbacks);
+
+                remote.fetch(&[&refspec], Some(&mut fetch_options), None)
+            })();
+
+            let _ = tx.send(FetchProgress::Done(result.map_err(|e| e.to_string())));
+        });
+
+        rx
+    }
+}
+
+/// Builds a `credentials` callback for `RemoteCallbacks`: try the system
+/// credential helper first, then fall back to a default SSH key on disk, then
+/// an SSH agent. Each fallback is attempted at most once per fetch so a bad
+/// key can't send libgit2 into a retry loop.
+fn build_credentials_callback(
+    config: git2::Config,
+) -> impl FnMut(&str, Option<&str>, CredentialType) -> Result<Cred, git2::Error> {
+    let mut credential_helper_tried = false;
+    let mut ssh_key_tried = false;
+    let mut ssh_agent_tried = false;
+
+    move |url, username_from_url, allowed_types| {
+        if !credential_helper_tried {
+            credential_helper_tried = true;
+            if let Ok(cred) = Cred::credential_helper(&config, url, username_from_url) {
+                return Ok(cred);
+            }
+        }
+
+        if allowed_types.contains(CredentialType::SSH_KEY) {
+            let username = username_from_url.unwrap_or("git");
+
+            if !ssh_key_tried {
+                ssh_key_tried = true;
+                if let Some(home) = dirs::home_dir() {
+                    for (private_key, public_key) in [
+                        (home.join(".ssh/id_rsa"), home.join(".ssh/id_rsa.pub")),
+                        (
+                            home.join(".ssh/id_ed25519"),
+                            home.join(".ssh/id_ed25519.pub"),
+                        ),
+                    ] {
+                        if !private_key.exists() {
+                            continue;
+                        }
+                        if let Ok(cred) =
+                            Cred::ssh_key(username, Some(&public_key), &private_key, None)
+                        {
+                            return Ok(cred);
+                        }
+                    }
+                }
+            }
+
+            if !ssh_agent_tried {
+                ssh_agent_tried = true;
+                if let Ok(cred) = Cred::ssh_key_from_agent(username) {
+                    return Ok(cred);
+                }
+            }
+        }
+
+        Err(git2::Error::from_str(
+            "No valid credentials found for remote",
+        ))
     }
 }
@@ -0,0 +1 @@
+pub mod branch_manager;
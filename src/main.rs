@@ -1,13 +1,20 @@
+mod config;
+mod git;
+mod ui;
+
 use std::io;
+use std::sync::mpsc;
+use std::time::Duration;
 
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Layout, Rect},
     style::{
-        palette::tailwind::{GREEN, RED, SLATE, WHITE},
+        palette::tailwind::{GREEN, RED, SLATE, WHITE, YELLOW},
         Modifier, Style, Stylize,
     },
+    text::{Line, Span},
     widgets::{
         Block, Borders, HighlightSpacing, List, ListItem, ListState, Paragraph, StatefulWidget,
         Widget,
@@ -15,7 +22,10 @@ use ratatui::{
     DefaultTerminal,
 };
 
-use git2::{Branch, Repository};
+use git2::Repository;
+
+use git::branch_manager::{BranchManager, FetchProgress, WorkingTreeState};
+use ui::controls::{Control, Controls};
 
 #[derive(Default)]
 enum StatusType {
@@ -50,13 +60,28 @@ impl Default for OperationStatus {
     }
 }
 
+/// Whether the app is browsing the branch list, editing the name of a
+/// branch being created, or asking to confirm a force switch.
+enum Mode {
+    Normal,
+    CreateBranch { input: String },
+    ConfirmForceSwitch { branch_name: String },
+}
+
 pub struct App<'repo> {
     branch_manager: BranchManager<'repo>,
     state: ListState,
     exit: bool,
     operation_status: OperationStatus,
+    fetch_progress_rx: Option<mpsc::Receiver<FetchProgress>>,
+    mode: Mode,
+    controls: Controls,
 }
 
+/// Poll interval used while a fetch is in flight, so progress updates and
+/// key presses are picked up promptly without a blocking `event::read`.
+const FETCH_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 impl<'a> Widget for &mut App<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let [header_area, main_area, footer_area] = Layout::vertical([
@@ -73,6 +98,13 @@ impl<'a> Widget for &mut App<'a> {
         self.render_header(title_area, buf);
         self.render_status(status_area, buf);
         self.render_body(main_area, buf);
+        match &self.mode {
+            Mode::CreateBranch { input } => render_create_branch_overlay(input, main_area, buf),
+            Mode::ConfirmForceSwitch { branch_name } => {
+                render_confirm_force_switch_overlay(branch_name, main_area, buf)
+            }
+            Mode::Normal => {}
+        }
         self.render_footer(footer_area, buf);
     }
 }
@@ -84,39 +116,117 @@ const BRANCH_EMOJI_WITH_SPACE: &str = " ";
 impl<'repo> App<'repo> {
     fn new(repo: &'repo Repository) -> Result<Self, git2::Error> {
         let branch_manager = BranchManager::new(repo)?;
+        let controls = Controls::new(config::load_key_bindings());
 
         Ok(App {
             state: ListState::default().with_selected(Some(0)),
             exit: false,
             branch_manager,
             operation_status: OperationStatus::default(),
+            fetch_progress_rx: None,
+            mode: Mode::Normal,
+            controls,
         })
     }
 
     fn run(mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
         while !self.exit {
+            // Drain before drawing so a fetch that just completed gets its
+            // "Fetch complete"/error status rendered this same iteration,
+            // rather than waiting for a draw on some later iteration that
+            // might be gated behind a blocking read.
+            self.drain_fetch_progress();
             terminal.draw(|frame| frame.render_widget(&mut self, frame.area()))?;
-            if let Event::Key(key) = event::read()? {
-                self.handle_key(key);
+
+            if self.fetch_progress_rx.is_some() {
+                // A fetch is running: poll on a short timeout so progress
+                // updates keep appearing even with no key presses.
+                if event::poll(FETCH_POLL_INTERVAL)? {
+                    if let Event::Key(key) = event::read()? {
+                        self.handle_key(key);
+                    }
+                }
+            } else {
+                // Idle: block until the next key press instead of
+                // busy-redrawing (each frame walks the working tree and
+                // re-sorts/recomputes ahead-behind for every branch).
+                if let Event::Key(key) = event::read()? {
+                    self.handle_key(key);
+                }
             }
         }
         Ok(())
     }
 
+    fn drain_fetch_progress(&mut self) {
+        let Some(rx) = &self.fetch_progress_rx else {
+            return;
+        };
+
+        // Drain into a buffer first: `rx` borrows `self`, and applying
+        // updates below needs `&mut self`.
+        let updates: Vec<FetchProgress> = rx.try_iter().collect();
+
+        let mut done = None;
+        for update in updates {
+            match update {
+                FetchProgress::Update {
+                    received_objects,
+                    total_objects,
+                    received_bytes,
+                } => {
+                    let percent = received_objects
+                        .checked_mul(100)
+                        .and_then(|n| n.checked_div(total_objects))
+                        .unwrap_or(0);
+                    self.set_status(
+                        &format!(
+                            "Fetching... {}% ({}/{} objects, {})",
+                            percent,
+                            received_objects,
+                            total_objects,
+                            format_bytes(received_bytes)
+                        ),
+                        StatusType::Info,
+                    );
+                }
+                FetchProgress::Done(result) => done = Some(result),
+            }
+        }
+
+        if let Some(result) = done {
+            self.fetch_progress_rx = None;
+            match result {
+                Ok(_) => self.set_status("Fetch complete", StatusType::Success),
+                Err(e) => self.set_status(&format!("Error fetching: {}", e), StatusType::Error),
+            }
+        }
+    }
+
     fn render_header(&mut self, area: Rect, buf: &mut Buffer) {
         let current_branch = match self.branch_manager.get_current_branch() {
             Ok(name) => name,
             Err(_) => "ERROR".to_string(),
         };
 
+        let (dirty_glyph, dirty_colour) = match self.branch_manager.working_tree_state() {
+            Ok(WorkingTreeState::Clean) => ("●", GREEN.c500),
+            Ok(WorkingTreeState::Conflicted) => ("✗", RED.c500),
+            Ok(_) => ("●", YELLOW.c500),
+            Err(_) => ("?", WHITE),
+        };
+
         let block = Block::default()
             .borders(Borders::ALL)
             .border_style(Style::default().fg(WHITE));
 
-        Paragraph::new(format!(
-            "Git Branch Explorer ({} {})",
-            BRANCH_EMOJI, current_branch
-        ))
+        Paragraph::new(Line::from(vec![
+            Span::styled(dirty_glyph, Style::default().fg(dirty_colour)),
+            Span::raw(format!(
+                " Git Branch Explorer ({} {})",
+                BRANCH_EMOJI, current_branch
+            )),
+        ]))
         .block(block)
         .bold()
         .centered()
@@ -161,33 +271,67 @@ impl<'repo> App<'repo> {
     }
 
     fn render_footer(&mut self, area: Rect, buf: &mut Buffer) {
-        Paragraph::new("Switch <s> | Fetch <f> | Refresh <r> | Quit <q>")
-            .bold()
-            .centered()
-            .render(area, buf);
+        let help_text = match self.mode {
+            Mode::Normal => self.controls.format_help(),
+            Mode::CreateBranch { .. } => "Confirm <Enter> | Cancel <Esc>".to_string(),
+            Mode::ConfirmForceSwitch { .. } => "Force switch? <y/n>".to_string(),
+        };
+
+        Paragraph::new(help_text).bold().centered().render(area, buf);
     }
 
     fn render_body(&mut self, area: Rect, buf: &mut Buffer) {
-        match self.branch_manager.get_all_local_branch_names() {
+        match self.branch_manager.get_branches_sorted() {
             Ok(branches) => {
                 let current_branch_name = match self.branch_manager.get_current_branch() {
                     Ok(name) => name,
                     Err(_) => String::from("None"),
                 };
 
+                // Leave room for the borders either side of the list.
+                let inner_width = area.width.saturating_sub(2) as usize;
+
                 let items = branches
                     .iter()
                     .enumerate()
-                    .map(|(i, branch_name)| {
+                    .map(|(i, branch)| {
                         let bg_colour = if i % 2 == 0 { SLATE.c950 } else { SLATE.c900 };
-                        let text_colour = if branch_name == &current_branch_name {
+                        let text_colour = if branch.name == current_branch_name {
                             GREEN.c500
                         } else {
                             WHITE
                         };
-                        ListItem::new(branch_name.clone())
-                            .bg(bg_colour)
-                            .fg(text_colour)
+
+                        let ahead_behind = branch
+                            .ahead_behind
+                            .map(|(ahead, behind)| format_ahead_behind(ahead, behind))
+                            .unwrap_or_default();
+
+                        let left = if ahead_behind.is_empty() {
+                            branch.name.clone()
+                        } else {
+                            format!("{} {}", branch.name, ahead_behind)
+                        };
+
+                        let age = branch
+                            .last_commit_time
+                            .map(format_relative_age)
+                            .unwrap_or_else(|| "-".to_string());
+                        // Use char counts, not byte lengths: `left` can
+                        // contain multi-byte ahead/behind arrows that each
+                        // still occupy a single display column.
+                        let padding = inner_width
+                            .saturating_sub(left.chars().count())
+                            .saturating_sub(age.chars().count())
+                            .max(1);
+
+                        let line = Line::from(vec![
+                            Span::raw(left),
+                            Span::raw(" ".repeat(padding)),
+                            Span::raw(age),
+                        ]);
+
+                        ListItem::new(line).bg(bg_colour).fg(text_colour)
                     })
                     .collect::<Vec<ListItem>>();
 
@@ -215,18 +359,66 @@ impl<'repo> App<'repo> {
             return;
         }
 
+        match self.mode {
+            Mode::Normal => self.handle_normal_key(key),
+            Mode::CreateBranch { .. } => self.handle_create_branch_key(key),
+            Mode::ConfirmForceSwitch { .. } => self.handle_confirm_force_switch_key(key),
+        }
+    }
+
+    fn handle_normal_key(&mut self, key: KeyEvent) {
+        match self.controls.handle_key(key.code) {
+            Some(Control::Quit) => self.exit = true,
+            Some(Control::Down) => self.select_next(),
+            Some(Control::Up) => self.select_previous(),
+            Some(Control::Switch) => self.switch_branch(),
+            Some(Control::Fetch) => self.fetch_branch(),
+            Some(Control::Create) => {
+                self.mode = Mode::CreateBranch {
+                    input: String::new(),
+                };
+            }
+            Some(Control::Refresh) => self.refresh_branches(),
+            None => {}
+        }
+    }
+
+    fn handle_create_branch_key(&mut self, key: KeyEvent) {
         match key.code {
-            KeyCode::Char('q') => self.exit = true,
-            KeyCode::Down => self.select_next(),
-            KeyCode::Up => self.select_previous(),
-            KeyCode::Char('s') => {
-                self.switch_branch();
+            KeyCode::Char(c) => {
+                if let Mode::CreateBranch { input } = &mut self.mode {
+                    input.push(c);
+                }
+            }
+            KeyCode::Backspace => {
+                if let Mode::CreateBranch { input } = &mut self.mode {
+                    input.pop();
+                }
             }
-            KeyCode::Char('f') => {
-                self.fetch_branch();
+            KeyCode::Enter => {
+                if let Mode::CreateBranch { input } = std::mem::replace(&mut self.mode, Mode::Normal)
+                {
+                    self.create_branch(input);
+                }
             }
-            KeyCode::Char('r') => {
-                self.refresh_branches();
+            KeyCode::Esc => {
+                self.mode = Mode::Normal;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_confirm_force_switch_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                if let Mode::ConfirmForceSwitch { branch_name } =
+                    std::mem::replace(&mut self.mode, Mode::Normal)
+                {
+                    self.attempt_switch(&branch_name, true);
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.mode = Mode::Normal;
             }
             _ => {}
         }
@@ -239,25 +431,48 @@ impl<'repo> App<'repo> {
         self.state.select_previous();
     }
 
+    fn selected_branch_name(&self) -> Option<String> {
+        let index = self.state.selected()?;
+        let branches = self.branch_manager.get_branches_sorted().ok()?;
+        branches.into_iter().nth(index).map(|branch| branch.name)
+    }
+
     fn switch_branch(&mut self) {
-        let branch_name = self
-            .state
-            .selected()
-            .and_then(|i| self.branch_manager.local_branches.get(i))
-            .and_then(|branch| branch.name().ok().flatten())
-            .unwrap_or("unknown branch")
-            .to_string();
+        let Some(branch_name) = self.selected_branch_name() else {
+            self.set_status("No branch selected", StatusType::Info);
+            return;
+        };
+
+        match self.branch_manager.working_tree_state() {
+            Ok(WorkingTreeState::Clean) => self.attempt_switch(&branch_name, false),
+            Ok(WorkingTreeState::Conflicted) => {
+                self.set_status(
+                    "Cannot switch: working tree has unresolved conflicts",
+                    StatusType::Error,
+                );
+            }
+            Ok(_) => {
+                self.mode = Mode::ConfirmForceSwitch { branch_name };
+            }
+            Err(e) => {
+                self.set_status(
+                    &format!("Error checking working tree: {}", e),
+                    StatusType::Error,
+                );
+            }
+        }
+    }
 
+    fn attempt_switch(&mut self, branch_name: &str, force: bool) {
         self.set_status(
             &format!("Switching to {}...", branch_name),
             StatusType::Info,
         );
 
         let result = self
-            .state
-            .selected()
-            .and_then(|i| self.branch_manager.local_branches.get(i))
-            .map(|branch| self.branch_manager.switch_to_branch(branch));
+            .branch_manager
+            .find_branch(branch_name)
+            .map(|branch| self.branch_manager.switch_to_branch(branch, force));
 
         match result {
             Some(Ok(_)) => {
@@ -279,139 +494,138 @@ impl<'repo> App<'repo> {
     }
 
     fn fetch_branch(&mut self) {
-        // Get the branch name first, before any status updates
-        let branch_name = self
-            .state
-            .selected()
-            .and_then(|i| self.branch_manager.local_branches.get(i))
-            .and_then(|branch| branch.name().ok().flatten())
-            .unwrap_or("unknown branch")
-            .to_string(); // Clone the string so we own it
-
-        // Now we can update status and use the branch
+        if self.fetch_progress_rx.is_some() {
+            self.set_status("Fetch already in progress", StatusType::Info);
+            return;
+        }
+
+        let Some(branch_name) = self.selected_branch_name() else {
+            self.set_status("No branch selected", StatusType::Info);
+            return;
+        };
+
         self.set_status(&format!("Fetching {}...", branch_name), StatusType::Info);
+        self.fetch_progress_rx = Some(self.branch_manager.spawn_fetch_with_progress(branch_name));
+    }
 
-        // Perform the fetch operation
-        let result = self
-            .state
-            .selected()
-            .and_then(|i| self.branch_manager.local_branches.get(i))
-            .map(|branch| self.branch_manager.fetch_on_branch(branch));
+    fn refresh_branches(&mut self) {
+        if let Err(e) = self.branch_manager.refresh_branches() {
+            eprintln!("Failed to refresh branches: {}", e);
+        }
+    }
 
-        // Update status based on result
-        match result {
-            Some(Ok(_)) => {
-                self.set_status(
-                    &format!("Successfully fetched {}", branch_name),
-                    StatusType::Success,
-                );
+    fn create_branch(&mut self, name: String) {
+        if name.is_empty() {
+            self.set_status("Branch name cannot be empty", StatusType::Error);
+            return;
+        }
+
+        match self.branch_manager.create_branch(&name) {
+            Ok(()) => {
+                self.select_branch_by_name(&name);
+                self.set_status(&format!("Created branch {}", name), StatusType::Success);
             }
-            Some(Err(e)) => {
+            Err(e) => {
                 self.set_status(
-                    &format!("Error fetching {}: {}", branch_name, e),
+                    &format!("Error creating branch {}: {}", name, e),
                     StatusType::Error,
                 );
             }
-            None => {
-                self.set_status("No branch selected", StatusType::Info);
-            }
         }
     }
 
-    fn refresh_branches(&mut self) {
-        if let Err(e) = self.branch_manager.refresh_branches() {
-            eprintln!("Failed to refresh branches: {}", e);
+    fn select_branch_by_name(&mut self, name: &str) {
+        if let Ok(branches) = self.branch_manager.get_branches_sorted() {
+            if let Some(index) = branches.iter().position(|branch| branch.name == name) {
+                self.state.select(Some(index));
+            }
         }
     }
 }
 
-struct BranchManager<'repo> {
-    repo: &'repo Repository,
-    local_branches: Vec<Branch<'repo>>,
-}
+/// Renders the branch-name input prompt over the branch list while the user
+/// is typing a new branch name.
+fn render_create_branch_overlay(input: &str, area: Rect, buf: &mut Buffer) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(WHITE))
+        .title("New branch name");
 
-impl<'repo> BranchManager<'repo> {
-    fn new(repo: &'repo Repository) -> Result<Self, git2::Error> {
-        let local_branches = repo
-            .branches(Some(git2::BranchType::Local))?
-            .filter_map(Result::ok)
-            .map(|(branch, _)| branch)
-            .collect();
-
-        Ok(Self {
-            repo,
-            local_branches,
-        })
-    }
+    Paragraph::new(format!("{}_", input))
+        .block(block)
+        .centered()
+        .render(area, buf);
+}
 
-    fn refresh_branches(&mut self) -> Result<(), git2::Error> {
-        self.local_branches = self
-            .repo
-            .branches(Some(git2::BranchType::Local))?
-            .filter_map(Result::ok)
-            .map(|(branch, _)| branch)
-            .collect::<Vec<_>>();
-        Ok(())
-    }
+/// Renders the confirmation prompt shown when switching branches would
+/// require a force checkout over local modifications.
+fn render_confirm_force_switch_overlay(branch_name: &str, area: Rect, buf: &mut Buffer) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(YELLOW.c500))
+        .title("Force switch?");
+
+    Paragraph::new(format!(
+        "Working tree has uncommitted changes. Force switch to {}? (y/n)",
+        branch_name
+    ))
+    .block(block)
+    .centered()
+    .render(area, buf);
+}
 
-    fn get_all_local_branch_names(&self) -> Result<Vec<String>, git2::Error> {
-        Ok(self
-            .local_branches
-            .iter()
-            .filter_map(|branch| branch.name().ok()?.map(String::from))
-            .collect())
+/// Formats a Unix epoch timestamp as a short relative age ("3h", "2d").
+fn format_relative_age(epoch_secs: i64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(epoch_secs);
+    let diff = (now - epoch_secs).max(0);
+
+    if diff < 60 {
+        format!("{}s", diff)
+    } else if diff < 60 * 60 {
+        format!("{}m", diff / 60)
+    } else if diff < 60 * 60 * 24 {
+        format!("{}h", diff / (60 * 60))
+    } else if diff < 60 * 60 * 24 * 30 {
+        format!("{}d", diff / (60 * 60 * 24))
+    } else if diff < 60 * 60 * 24 * 365 {
+        format!("{}mo", diff / (60 * 60 * 24 * 30))
+    } else {
+        format!("{}y", diff / (60 * 60 * 24 * 365))
     }
+}
 
-    fn get_current_branch(&self) -> Result<String, git2::Error> {
-        let head = self.repo.head()?;
-
-        if head.is_branch() {
-            Ok(head.shorthand().unwrap_or("HEAD").to_string())
-        } else {
-            // Detached head state
-            let commit = head.peel_to_commit()?;
-            Ok(commit.id().to_string())
-        }
+/// Formats an ahead/behind pair as a compact glyph ("↑3", "↓1", "↑3↓1"), or
+/// an empty string when the branch is in sync with its upstream.
+fn format_ahead_behind(ahead: usize, behind: usize) -> String {
+    match (ahead, behind) {
+        (0, 0) => String::new(),
+        (ahead, 0) => format!("↑{}", ahead),
+        (0, behind) => format!("↓{}", behind),
+        (ahead, behind) => format!("↑{}↓{}", ahead, behind),
     }
+}
 
-    fn switch_to_branch(&self, branch: &Branch) -> Result<(), git2::Error> {
-        let branch_name = branch
-            .name()?
-            .ok_or_else(|| git2::Error::from_str("Invalid UTF-8 in branch name."))?;
-
-        let current_head_name = self.get_current_branch()?;
+/// Formats a byte count using the largest unit that keeps the number >= 1.
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = UNITS[0];
 
-        let statuses = self.repo.statuses(None)?;
-        if !statuses.is_empty() {
-            return Err(git2::Error::from_str(&format!(
-                "Uncommitted local changes on branch {}",
-                current_head_name,
-            )));
+    for next_unit in &UNITS[1..] {
+        if value < 1024.0 {
+            break;
         }
-
-        let mut opts = git2::build::CheckoutBuilder::new();
-
-        self.repo.set_head(&format!("refs/heads/{}", branch_name))?;
-        self.repo.checkout_head(Some(&mut opts))
+        value /= 1024.0;
+        unit = next_unit;
     }
 
-    fn fetch_on_branch(&self, branch: &Branch) -> Result<(), git2::Error> {
-        let branch_name = branch
-            .name()?
-            .ok_or_else(|| git2::Error::from_str("Invalid UTF-8 in branch name"))?;
-
-        let mut remote = self.repo.find_remote("origin")?;
-        let refspec = format!(
-            "+refs/heads/{}:refs/remotes/origin/{}",
-            branch_name, branch_name
-        );
-
-        let mut fetch_options = git2::FetchOptions::new();
-        fetch_options.download_tags(git2::AutotagOption::None);
-
-        remote.fetch(&[&refspec], Some(&mut fetch_options), None)?;
-
-        Ok(())
+    if unit == UNITS[0] {
+        format!("{} {}", bytes, unit)
+    } else {
+        format!("{:.1} {}", value, unit)
     }
 }
 
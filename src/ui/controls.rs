@@ -1,9 +1,12 @@
 use crossterm::event::KeyCode;
 
+use crate::config::KeyBindings;
+
 #[derive(Copy, Clone)]
 pub enum Control {
     Switch,
     Fetch,
+    Create,
     Refresh,
     Up,
     Down,
@@ -11,14 +14,17 @@ pub enum Control {
 }
 
 impl Control {
-    pub fn key(&self) -> KeyCode {
+    pub fn key(&self, bindings: &KeyBindings) -> KeyCode {
         match self {
-            Control::Switch => KeyCode::Char('s'),
-            Control::Fetch => KeyCode::Char('f'),
-            Control::Refresh => KeyCode::Char('r'),
-            Control::Up => KeyCode::Up,
-            Control::Down => KeyCode::Down,
-            Control::Quit => KeyCode::Char('q'),
+            Control::Switch => bindings.switch,
+            Control::Fetch => bindings.fetch,
+            // Create isn't in `KeyBindings` — it's reserved and not
+            // user-remappable, see `config::parse_key`.
+            Control::Create => KeyCode::Char('c'),
+            Control::Refresh => bindings.refresh,
+            Control::Up => bindings.up,
+            Control::Down => bindings.down,
+            Control::Quit => bindings.quit,
         }
     }
 
@@ -26,6 +32,7 @@ impl Control {
         match self {
             Control::Switch => "Switch",
             Control::Fetch => "Fetch",
+            Control::Create => "Create",
             Control::Refresh => "Refresh",
             Control::Up => "Up",
             Control::Down => "Down",
@@ -33,11 +40,17 @@ impl Control {
         }
     }
 
-    pub fn format_key(&self) -> String {
-        let key_text = match self.key() {
+    pub fn format_key(&self, bindings: &KeyBindings) -> String {
+        let key_text = match self.key(bindings) {
             KeyCode::Char(c) => c.to_string(),
             KeyCode::Up => "↑".to_string(),
             KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::Backspace => "Backspace".to_string(),
             _ => String::new(),
         };
         format!("<{}>", key_text)
@@ -46,26 +59,35 @@ impl Control {
 
 pub struct Controls {
     controls: Vec<Control>,
+    bindings: KeyBindings,
 }
 
 impl Controls {
-    pub fn new() -> Self {
+    pub fn new(bindings: KeyBindings) -> Self {
         Self {
             controls: vec![
                 Control::Switch,
                 Control::Fetch,
+                Control::Create,
                 Control::Refresh,
                 Control::Up,
                 Control::Down,
                 Control::Quit,
             ],
+            bindings,
         }
     }
 
     pub fn format_help(&self) -> String {
         self.controls
             .iter()
-            .map(|control| format!("{} {}", control.display_name(), control.format_key()))
+            .map(|control| {
+                format!(
+                    "{} {}",
+                    control.display_name(),
+                    control.format_key(&self.bindings)
+                )
+            })
             .collect::<Vec<_>>()
             .join(" | ")
     }
@@ -73,7 +95,7 @@ impl Controls {
     pub fn handle_key(&self, code: KeyCode) -> Option<Control> {
         self.controls
             .iter()
-            .find(|control| control.key() == code)
+            .find(|control| control.key(&self.bindings) == code)
             .copied()
     }
 }